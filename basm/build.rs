@@ -0,0 +1,210 @@
+//! Generates the `Instruction`/`RawInstruction` enum variants, the parser's `dispatch!` arms
+//! and the `write_instruction` match arms from `instructions.in`, so adding an instruction is a
+//! single table line instead of three hand-edited match statements.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// One parsed row of `instructions.in`
+struct Row {
+    mnemonic: String,
+    variant: String,
+    params: Params,
+    emit: Emit,
+}
+
+/// The parameter shape of an instruction
+enum Params {
+    None,
+    U32,
+    Cell,
+    CellCell,
+}
+
+/// How an instruction's brainfuck is emitted
+enum Emit {
+    /// A literal, fixed brainfuck snippet
+    Literal(String),
+    /// A hand-written function in lib.rs, named in full by the table's `fn:` entry
+    Function(String),
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=instructions.in");
+
+    let table = fs::read_to_string("instructions.in").expect("failed to read instructions.in");
+    let rows: Vec<Row> = table.lines().filter_map(parse_row).collect();
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
+    fs::write(Path::new(&out_dir).join("enums.rs"), gen_enums(&rows)).unwrap();
+    fs::write(Path::new(&out_dir).join("dispatch.rs"), gen_dispatch(&rows)).unwrap();
+    fs::write(
+        Path::new(&out_dir).join("write_instruction.rs"),
+        gen_write_instruction(&rows),
+    )
+    .unwrap();
+}
+
+/// Parse a single `instructions.in` line, skipping blanks and `#` comments
+fn parse_row(line: &str) -> Option<Row> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let [mnemonic, variant, params, emit] = fields[..] else {
+        panic!("malformed instructions.in line: {line}");
+    };
+
+    let params = match params {
+        "none" => Params::None,
+        "u32" => Params::U32,
+        "cell" => Params::Cell,
+        "cell,cell" => Params::CellCell,
+        other => panic!("unknown param shape `{other}` in instructions.in"),
+    };
+
+    let emit = match emit.split_once(':') {
+        Some(("lit", lit)) => Emit::Literal(lit.to_owned()),
+        Some(("fn", name)) => Emit::Function(name.to_owned()),
+        _ => panic!("unknown emit `{emit}` in instructions.in"),
+    };
+
+    Some(Row {
+        mnemonic: mnemonic.to_owned(),
+        variant: variant.to_owned(),
+        params,
+        emit,
+    })
+}
+
+/// Generate the `Instruction` and `RawInstruction` enum definitions
+fn gen_enums(rows: &[Row]) -> String {
+    let mut instruction = String::from(
+        "/// A single Instruction that will be compiled to brainfuck\n\
+         #[derive(Debug, Clone, Copy)]\n\
+         pub enum Instruction {\n",
+    );
+    let mut raw = String::from(
+        "/// An instruction as produced by the parser, before named cells have been resolved\n\
+         /// to offsets by `resolve`\n\
+         #[derive(Debug, Clone)]\n\
+         pub enum RawInstruction {\n",
+    );
+
+    for row in rows {
+        instruction.push_str(&format!(
+            "    /// Generated from the `{}` instruction\n",
+            row.mnemonic
+        ));
+        instruction.push_str(&format!(
+            "    {},\n",
+            variant_decl(&row.variant, &row.params, false)
+        ));
+
+        raw.push_str(&format!(
+            "    /// Generated from the `{}` instruction\n",
+            row.mnemonic
+        ));
+        raw.push_str(&format!(
+            "    {},\n",
+            variant_decl(&row.variant, &row.params, true)
+        ));
+    }
+
+    instruction.push_str("}\n\n");
+    raw.push_str(
+        "    /// Declare a named cell, allocating the next free tape cell for it\n    Var(String),\n}\n",
+    );
+
+    format!("{instruction}{raw}")
+}
+
+/// Render an enum variant declaration for either the resolved (`i32`) or raw (`CellRef`) shape
+fn variant_decl(variant: &str, params: &Params, raw: bool) -> String {
+    let cell_ty = if raw { "CellRef" } else { "i32" };
+    match params {
+        Params::None => variant.to_owned(),
+        Params::U32 => format!("{variant}(u32)"),
+        Params::Cell => format!("{variant}({cell_ty})"),
+        Params::CellCell => format!("{variant}({cell_ty}, {cell_ty})"),
+    }
+}
+
+/// Generate the `parse_instruction` function
+fn gen_dispatch(rows: &[Row]) -> String {
+    let mut arms = String::new();
+
+    for row in rows {
+        let mnemonic = &row.mnemonic;
+        let variant = &row.variant;
+        let arm = match row.params {
+            Params::None => format!(
+                "        \"{mnemonic}\" => success(Some(RawInstruction::{variant})),\n"
+            ),
+            Params::U32 => format!(
+                "        \"{mnemonic}\" => parse_u32_param.map(RawInstruction::{variant}).map(Some),\n"
+            ),
+            Params::Cell => format!(
+                "        \"{mnemonic}\" => parse_cell_ref.map(RawInstruction::{variant}).map(Some),\n"
+            ),
+            Params::CellCell => format!(
+                "        \"{mnemonic}\" => (\n\
+                 \x20           parse_cell_ref,\n\
+                 \x20           delimited(multispace0, ',', multispace0).void(),\n\
+                 \x20           parse_cell_ref\n\
+                 \x20       ).map(|(first, _, second)| Some(RawInstruction::{variant}(first, second))),\n"
+            ),
+        };
+        arms.push_str(&arm);
+    }
+
+    format!(
+        "fn parse_instruction(input: &str) -> IResult<&str, Option<RawInstruction>> {{\n\
+         \x20   dispatch! {{parse_word;\n\
+         {arms}\
+         \x20       \"VAR\" => parse_name.map(RawInstruction::Var).map(Some),\n\
+         \x20       \";\" => take_until0(\"\\n\").map(|_| None),\n\
+         \x20       _ => {{\n\
+         \x20           println!(\"bad word\");\n\
+         \x20           fail\n\
+         \x20       }}\n\
+         \x20   }}\n\
+         \x20   .parse_next(input)\n\
+         }}\n"
+    )
+}
+
+/// Generate the `write_instruction` function
+fn gen_write_instruction(rows: &[Row]) -> String {
+    let mut arms = String::new();
+
+    for row in rows {
+        let variant = &row.variant;
+        let arm = match (&row.params, &row.emit) {
+            (Params::None, Emit::Literal(lit)) => {
+                format!("        Instruction::{variant} => writer.write_all(b\"{lit}\")?,\n")
+            }
+            (Params::U32 | Params::Cell, Emit::Function(name)) => {
+                format!("        Instruction::{variant}(n) => {name}(writer, n)?,\n")
+            }
+            (Params::CellCell, Emit::Function(name)) => {
+                format!("        Instruction::{variant}(a, b) => {name}(writer, a, b)?,\n")
+            }
+            _ => panic!("instruction `{variant}` combines an unsupported params/emit pair"),
+        };
+        arms.push_str(&arm);
+    }
+
+    format!(
+        "/// Compile a single instruction to brainfuck\n\
+         fn write_instruction<W: Write>(writer: &mut W, instruction: &Instruction) -> Result<(), W::Error> {{\n\
+         \x20   match *instruction {{\n\
+         {arms}\
+         \x20   }}\n\n\
+         \x20   Ok(())\n\
+         }}\n"
+    )
+}
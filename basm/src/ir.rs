@@ -0,0 +1,20 @@
+//! The instruction types shared by the parser, the resolver and the compiler.
+//!
+//! Kept separate from [`crate::parser`] so that [`Instruction`], the form [`crate::compile`]
+//! consumes, stays available with the `std` feature off even though parsing itself needs `std`
+//! (`winnow`) and resolving named cells needs a hash map.
+
+use alloc::string::String;
+
+/// A cell parameter to a [`RawInstruction`], either an already cursor-relative offset or a
+/// reference to a cell declared with `VAR`, to be resolved by [`crate::resolve`]
+#[derive(Debug, Clone)]
+pub enum CellRef {
+    /// A raw, already cursor-relative offset
+    Offset(i32),
+    /// A reference to a cell declared with `VAR`
+    Name(String),
+}
+
+// `Instruction` and `RawInstruction` are generated from `instructions.in` by build.rs
+include!(concat!(env!("OUT_DIR"), "/enums.rs"));
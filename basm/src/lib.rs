@@ -0,0 +1,204 @@
+//! A super simple assembly like language that compiles to brainfuck.
+//!
+//! This was mainly written to learn a bit about winnow. See `main.rs` for the CLI that drives
+//! this library's `parse` -> `resolve` -> `compile` pipeline.
+//!
+//! The compiler itself is `#![no_std]` behind the default `std` feature (the `winnow`-based
+//! parser still needs `std`, since winnow does). With `std` off, [`compile`] writes through the
+//! [`Write`] trait below instead of `std::io::Write`, mirroring `brainfuck_interpreter`'s VM.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod ir;
+
+// The winnow-based parser and its hash-map-backed resolution pass need `std`; `Instruction` and
+// the lower-level `compile` below do not, so they stay available with `std` off.
+#[cfg(feature = "std")]
+pub mod parser;
+#[cfg(feature = "std")]
+pub mod resolve;
+
+use core::cmp;
+
+#[cfg(feature = "std")]
+use alloc::{string::String, vec::Vec};
+pub use ir::{CellRef, Instruction, RawInstruction};
+#[cfg(feature = "std")]
+pub use parser::{parse, ParseError};
+#[cfg(feature = "std")]
+pub use resolve::{resolve, ResolveError};
+
+/// A writer abstraction so the compiler can run under `no_std`. Implemented for every
+/// `std::io::Write` when the `std` feature is enabled.
+pub trait Write {
+    /// The error produced by a failed write
+    type Error;
+
+    /// Write the given bytes
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error>;
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Write for W {
+    type Error = std::io::Error;
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        std::io::Write::write_all(self, buf)
+    }
+}
+
+/// Instruction to clear a register
+const CLEAR_REG: &[u8] = b"[-]";
+
+/// Compile a list of instructions to brainfuck
+pub fn compile<W: Write>(writer: &mut W, instructions: &[Instruction]) -> Result<(), W::Error> {
+    for instruction in instructions {
+        write_instruction(writer, instruction)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Compile a list of instructions to brainfuck, returning it as a `String`
+#[cfg(feature = "std")]
+pub fn compile_to_string(instructions: &[Instruction]) -> String {
+    let mut buf = Vec::new();
+    compile(&mut buf, instructions).expect("writing to a Vec<u8> never fails");
+    String::from_utf8(buf).expect("compiled brainfuck is always valid utf8")
+}
+
+// `write_instruction` is generated from `instructions.in` by build.rs
+include!(concat!(env!("OUT_DIR"), "/write_instruction.rs"));
+
+/// Write the brainfuck for `WRITE`: clear the current cell then increment it `n` times
+fn write_write<W: Write>(writer: &mut W, n: u32) -> Result<(), W::Error> {
+    writer.write_all(CLEAR_REG)?;
+    for _ in 0..n {
+        writer.write_all(b"+")?;
+    }
+
+    Ok(())
+}
+
+/// Write the brainfuck for `MOVE`: move the cursor `n` cells, left if negative
+fn write_move_instr<W: Write>(writer: &mut W, n: i32) -> Result<(), W::Error> {
+    let sign = if n < 0i32 { b"<" } else { b">" };
+
+    write_multiple(writer, sign.as_slice(), n.abs())
+}
+
+/// Write the brainfuck for `MOVEVAL`: move the current cell's value to the cell `n` away
+fn write_move_value<W: Write>(writer: &mut W, n: i32) -> Result<(), W::Error> {
+    let (sign_fwd, sign_back) = if n > 0i32 { (b">", b"<") } else { (b"<", b">") };
+    let n = n.abs();
+
+    // set other cell zero
+    write_multiple(writer, sign_fwd, n)?;
+    writer.write_all(CLEAR_REG)?;
+    write_multiple(writer, sign_back, n)?;
+
+    write_move_val_loop(writer, n, sign_fwd, sign_back)
+}
+
+/// Write the brainfuck for `COPY`: copy the current cell's value into `to`, using `tmp` as a
+/// scratch cell
+///
+/// This works pretty much as:
+/// `to` = 0; `tmp` = 0; move(`original`, both(`tmp`, `to`)); move(`tmp`, `original`);
+fn write_copy_value<W: Write>(writer: &mut W, to: i32, tmp: i32) -> Result<(), W::Error> {
+    // Move to the to register and clear it
+    write_move(writer, to)?;
+    writer.write_all(CLEAR_REG)?;
+    // Move to the `tmp` register from the `to` register and clear it
+    write_move(writer, tmp.wrapping_sub(to))?;
+    writer.write_all(CLEAR_REG)?;
+    // Move back to the `original` register
+    write_move(writer, tmp.wrapping_neg())?;
+    // start loop
+    writer.write_all(b"[-")?;
+    // Increment the `to` register by one
+    write_move(writer, to)?;
+    writer.write_all(b"+")?;
+    // Increment the `tmp` register by one
+    write_move(writer, tmp.wrapping_sub(to))?;
+    writer.write_all(b"+")?;
+    // Go back to the `original` register
+    write_move(writer, tmp.wrapping_neg())?;
+    // End the loop
+    writer.write_all(b"]")?;
+    // Move the value from the `tmp` register back to the `original` register
+    write_move(writer, tmp)?;
+    write_move_val_loop(
+        writer,
+        tmp.abs(),
+        move_sign_of(tmp.wrapping_neg()),
+        move_sign_of(tmp),
+    )?;
+    write_move(writer, tmp.wrapping_neg())
+}
+
+/// Write a movement of `moven` places negative means move to the left and positive to the right
+fn write_move<W: Write>(writer: &mut W, moven: i32) -> Result<(), W::Error> {
+    match moven.cmp(&0i32) {
+        cmp::Ordering::Equal => Ok(()),
+        cmp::Ordering::Greater => write_multiple(writer, b">", moven),
+        cmp::Ordering::Less => write_multiple(writer, b"<", moven.abs()),
+    }
+}
+
+/// Write a loop to move a value over to another register
+fn write_move_val_loop<W: Write>(
+    writer: &mut W,
+    dist: i32,
+    sign_fwd: &[u8],
+    sign_back: &[u8],
+) -> Result<(), W::Error> {
+    // start loop
+    writer.write_all(b"[-")?;
+    // carry over a one to the other cell
+    write_multiple(writer, sign_fwd, dist)?;
+    writer.write_all(b"+")?;
+    write_multiple(writer, sign_back, dist)?;
+    // end the loop
+    writer.write_all(b"]")
+}
+
+/// Get the correct movement sign `>` for positive and `<` for negative
+fn move_sign_of(n: i32) -> &'static [u8; 1] {
+    if n >= 0i32 {
+        b">"
+    } else {
+        b"<"
+    }
+}
+
+/// Write the given bytes multiple times
+fn write_multiple<W: Write>(writer: &mut W, bytes: &[u8], n: i32) -> Result<(), W::Error> {
+    for _ in 0i32..n {
+        writer.write_all(bytes)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Round-trips a basm program all the way to the brainfuck VM's output, exercising the
+    /// `parse` -> `resolve` -> `compile_to_string` pipeline against `brainfuck_interpreter::run`
+    #[test]
+    fn write_and_print_round_trips_through_the_vm() {
+        let raw = parse("WRITE 'A' PRINT").expect("valid basm");
+        let instructions = resolve(&raw).expect("no named cells to resolve");
+        let brainfuck = compile_to_string(&instructions);
+
+        let mut output = Vec::new();
+        brainfuck_interpreter::run(brainfuck.as_bytes(), &b""[..], &mut output)
+            .expect("valid brainfuck");
+
+        assert_eq!(output, b"A");
+    }
+}
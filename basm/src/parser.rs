@@ -1,6 +1,6 @@
 //! Parser for a basm program
 
-use std::fmt::Display;
+use std::fmt::{self, Display};
 
 pub use winnow::error::ErrMode as ParserError;
 
@@ -15,45 +15,30 @@ use winnow::{
     sequence::delimited,
 };
 
-/// A single Instruction that will be compiled to brainfuck
-#[derive(Debug, Clone, Copy)]
-pub enum Instruction {
-    /// Receive input from the user and store it in the current cell (`,`)
-    Input,
-    /// Print the current cells value (`.`)
-    Print,
-    /// Write the given value into the current cell after clearing it
-    Write(u32),
-    /// Move to another cell, move right if positive (`>`) and move left if negative (`<`)
-    Move(i32),
-    /// Move a value from the current cell to the given offset.
-    MoveValue(i32),
-    /// Copy a given value
-    CopyValue(i32, i32),
+pub use crate::ir::{CellRef, Instruction, RawInstruction};
+
+/// An error produced while parsing a basm program
+#[derive(Debug)]
+pub enum ParseError<'a> {
+    /// The underlying winnow parser failed
+    Winnow(ParserError<WinnowError<&'a str>>),
+    /// A `WHILE` was never closed by a matching `END`, or an `END` appeared without an
+    /// opening `WHILE`
+    UnmatchedLoop,
 }
 
-/// Parse a single instruction
-fn parse_instruction(input: &str) -> IResult<&str, Option<Instruction>> {
-    dispatch! {parse_word;
-        "INPUT" => success(Some(Instruction::Input)),
-        "PRINT" => success(Some(Instruction::Print)),
-        "WRITE" => parse_u32_param.map(Instruction::Write).map(Some),
-        "MOVE" => parse_i32_param.map(Instruction::Move).map(Some),
-        "MOVEVAL" => parse_i32_param.map(Instruction::MoveValue).map(Some),
-        "COPY" => (
-            parse_i32_param,
-            delimited(multispace0, ',', multispace0).void(),
-            parse_i32_param
-        ).map(|(first, _, second)| Some(Instruction::CopyValue(first, second))),
-        ";" => take_until0("\n").map(|_| None),
-        _ => {
-            println!("bad word");
-            fail
+impl Display for ParseError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Winnow(e) => write!(f, "{}", e),
+            ParseError::UnmatchedLoop => write!(f, "unmatched WHILE/END"),
         }
     }
-    .parse_next(input)
 }
 
+// `parse_instruction` is generated from `instructions.in` by build.rs
+include!(concat!(env!("OUT_DIR"), "/dispatch.rs"));
+
 /// Parses a u32 parameter to an instruction
 fn parse_u32_param(input: &str) -> IResult<&str, u32> {
     alt((
@@ -69,6 +54,20 @@ fn parse_i32_param(input: &str) -> IResult<&str, i32> {
     dec_int.parse_next(input)
 }
 
+/// Parses a cell parameter to an instruction, either a raw offset or a named cell
+fn parse_cell_ref(input: &str) -> IResult<&str, CellRef> {
+    alt((
+        parse_i32_param.map(CellRef::Offset),
+        parse_name.map(CellRef::Name),
+    ))
+    .parse_next(input)
+}
+
+/// Parses a variable name
+fn parse_name(input: &str) -> IResult<&str, String> {
+    alpha1.map(String::from).parse_next(input)
+}
+
 /// Parses an escape sequence
 fn parse_escape(input: &str) -> IResult<&str, u32> {
     let (rest, c) = delimited("'\\", any, '\'').parse_next(input)?;
@@ -85,13 +84,14 @@ fn parse_word(input: &str) -> IResult<&str, &str> {
     delimited(multispace0, alt((alpha1, ";")), multispace0).parse_next(input)
 }
 
-/// Parse the given basm string
-pub fn parse(s: &str) -> Result<Vec<Instruction>, impl Display + '_> {
+/// Parse the given basm string into the raw instruction stream. Named cell references are not
+/// yet resolved to offsets; pass the result through [`crate::resolve::resolve`] before compiling.
+pub fn parse(s: &str) -> Result<Vec<RawInstruction>, ParseError<'_>> {
     // let words = many0(parse_instruction).parse_next(s).unwrap();
     let words = fold_many0(
         parse_instruction,
         Vec::new,
-        |mut acc: Vec<Instruction>, item| {
+        |mut acc: Vec<RawInstruction>, item| {
             if let Some(item) = item {
                 acc.push(item);
             }
@@ -101,5 +101,33 @@ pub fn parse(s: &str) -> Result<Vec<Instruction>, impl Display + '_> {
     )
     .parse_next(s);
     // eprintln!("{:?}", words);
-    words.map(|(_, a)| a)
+    let instructions = words.map(|(_, a)| a).map_err(ParseError::Winnow)?;
+    check_loop_nesting(&instructions)?;
+
+    Ok(instructions)
+}
+
+/// Track `WHILE`/`END` nesting depth across the parsed instructions, rejecting an unmatched
+/// `WHILE` or a stray `END` before it turns into malformed brainfuck brackets
+fn check_loop_nesting(instructions: &[RawInstruction]) -> Result<(), ParseError<'static>> {
+    let mut depth = 0i32;
+
+    for instruction in instructions {
+        match instruction {
+            RawInstruction::LoopStart => depth += 1,
+            RawInstruction::LoopEnd => {
+                depth -= 1;
+                if depth < 0 {
+                    return Err(ParseError::UnmatchedLoop);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if depth != 0 {
+        return Err(ParseError::UnmatchedLoop);
+    }
+
+    Ok(())
 }
@@ -0,0 +1,127 @@
+//! Resolves named cells declared with `VAR` into the cursor-relative offsets the compiler
+//! already understands.
+//!
+//! The parser produces a [`RawInstruction`] stream where `MOVE`/`MOVEVAL`/`COPY` may reference
+//! a cell by name instead of by offset. This pass walks that stream once, tracking the current
+//! cell the program would be sitting on and the cell each `VAR` name was allocated to, and
+//! rewrites every reference into the offset-based [`Instruction`] the compiler expects.
+//!
+//! `VAR` allocation is independent of raw `MOVE <int>` offsets: both start counting from cell
+//! `0`, so a `VAR` can alias a cell a raw offset also reaches. Declare variables before using any
+//! raw offsets in the same program to avoid the collision.
+
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use crate::parser::{CellRef, Instruction, RawInstruction};
+
+/// An error produced while resolving named cells
+#[derive(Debug)]
+pub enum ResolveError {
+    /// A `VAR` declared a name that was already in use
+    DuplicateName(String),
+    /// A cell reference used a name that was never declared with `VAR`
+    UndefinedName(String),
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::DuplicateName(name) => {
+                write!(f, "variable `{}` is already defined", name)
+            }
+            ResolveError::UndefinedName(name) => write!(f, "variable `{}` is not defined", name),
+        }
+    }
+}
+
+/// Resolve named cell references into cursor-relative offsets, producing the instruction
+/// stream the compiler already knows how to emit
+pub fn resolve(raw: &[RawInstruction]) -> Result<Vec<Instruction>, ResolveError> {
+    let mut cells = Cells::new();
+    let mut instructions = Vec::with_capacity(raw.len());
+
+    for instruction in raw {
+        let instruction = match instruction {
+            RawInstruction::Input => Some(Instruction::Input),
+            RawInstruction::Print => Some(Instruction::Print),
+            RawInstruction::Write(n) => Some(Instruction::Write(*n)),
+            RawInstruction::LoopStart => Some(Instruction::LoopStart),
+            RawInstruction::LoopEnd => Some(Instruction::LoopEnd),
+            RawInstruction::Move(to) => {
+                let offset = cells.offset_to(to)?;
+                cells.move_cursor(offset);
+                Some(Instruction::Move(offset))
+            }
+            RawInstruction::MoveValue(to) => Some(Instruction::MoveValue(cells.offset_to(to)?)),
+            RawInstruction::CopyValue(to, tmp) => {
+                let to = cells.offset_to(to)?;
+                let tmp = cells.offset_to(tmp)?;
+                Some(Instruction::CopyValue(to, tmp))
+            }
+            RawInstruction::Var(name) => {
+                cells.declare(name)?;
+                None
+            }
+        };
+
+        if let Some(instruction) = instruction {
+            instructions.push(instruction);
+        }
+    }
+
+    Ok(instructions)
+}
+
+/// Tracks the tape cursor and the named-cell symbol table while resolving
+struct Cells {
+    /// Absolute index of the cell the program is currently sitting on
+    current: i32,
+    /// Absolute index of the next free cell a `VAR` will allocate
+    next_free: usize,
+    /// Names declared with `VAR`, mapped to the absolute cell they were allocated to
+    names: HashMap<String, usize>,
+}
+
+impl Cells {
+    /// Create a fresh, empty symbol table with the cursor at cell `0`
+    fn new() -> Self {
+        Self {
+            current: 0,
+            next_free: 0,
+            names: HashMap::new(),
+        }
+    }
+
+    /// Allocate the next free cell for `name`, erroring if it is already declared
+    fn declare(&mut self, name: &str) -> Result<(), ResolveError> {
+        if self.names.contains_key(name) {
+            return Err(ResolveError::DuplicateName(name.to_owned()));
+        }
+
+        self.names.insert(name.to_owned(), self.next_free);
+        self.next_free += 1;
+
+        Ok(())
+    }
+
+    /// Resolve a [`CellRef`] to an offset relative to the current cursor, without moving it
+    fn offset_to(&self, to: &CellRef) -> Result<i32, ResolveError> {
+        match to {
+            CellRef::Offset(n) => Ok(*n),
+            CellRef::Name(name) => {
+                let target = *self
+                    .names
+                    .get(name)
+                    .ok_or_else(|| ResolveError::UndefinedName(name.clone()))?;
+
+                Ok(target as i32 - self.current)
+            }
+        }
+    }
+
+    /// Move the cursor by the given resolved offset
+    fn move_cursor(&mut self, offset: i32) {
+        self.current += offset;
+    }
+}
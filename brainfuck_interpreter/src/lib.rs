@@ -0,0 +1,387 @@
+//! Library API for compiling and executing brainfuck programs.
+//!
+//! This reads the whole programm up front and compiles it into a compact op vector with a
+//! precomputed bracket jump table, so loops execute in O(1) per jump instead of rescanning the
+//! source on every iteration.
+//!
+//! The crate is `#![no_std]` behind the default `std` feature, so it can run on bare-metal or
+//! firmware hosts. With `std` off it still needs `alloc` for the tape and the op vector; reading
+//! and writing go through the [`Read`]/[`Write`] traits below instead of `std::io`, mirroring
+//! how the `core_io` crate mirrors `std::io::{Read, Write}` for `no_std` crates.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{format, vec, vec::Vec};
+use core::fmt::{self, Display};
+
+/// A reader abstraction so the VM can run under `no_std`. Implemented for every
+/// `std::io::Read` when the `std` feature is enabled.
+pub trait Read {
+    /// The error produced by a failed read
+    type Error;
+
+    /// Read a single byte, returning `None` once the input is exhausted
+    fn read_byte(&mut self) -> Result<Option<u8>, Self::Error>;
+}
+
+/// A writer abstraction so the VM can run under `no_std`. Implemented for every
+/// `std::io::Write` when the `std` feature is enabled.
+pub trait Write {
+    /// The error produced by a failed write
+    type Error;
+
+    /// Write the given bytes
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error>;
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Read for R {
+    type Error = std::io::Error;
+
+    fn read_byte(&mut self) -> Result<Option<u8>, Self::Error> {
+        let mut buf = [0u8; 1];
+        match std::io::Read::read(self, &mut buf) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(buf[0])),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Write for W {
+    type Error = std::io::Error;
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        std::io::Write::write_all(self, buf)
+    }
+}
+
+/// An error produced while compiling or executing a brainfuck program
+#[derive(Debug)]
+pub enum RunError<RE, WE> {
+    /// Reading input failed
+    Input(RE),
+    /// Writing output failed
+    Output(WE),
+    /// The program could not be compiled
+    Compile(CompileError),
+}
+
+impl<RE: Display, WE: Display> Display for RunError<RE, WE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunError::Input(e) => write!(f, "{}", e),
+            RunError::Output(e) => write!(f, "{}", e),
+            RunError::Compile(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// How the `,` instruction behaves once the input is exhausted
+#[derive(Debug, Clone, Copy)]
+pub enum EofPolicy {
+    /// Leave the current cell's value unchanged
+    Leave,
+    /// Set the current cell to `0`
+    Zero,
+    /// Set the current cell to `u32::MAX` (a brainfuck dialect's `-1`)
+    NegOne,
+}
+
+/// How the `.` instruction renders a cell's value
+#[derive(Debug, Clone, Copy)]
+pub enum OutputMode {
+    /// Print the cell as a unicode codepoint when it is one, falling back to a raw number
+    Utf8,
+    /// Print the cell as a raw decimal number followed by a space
+    Decimal,
+    /// Print the cell truncated to a single output byte
+    Byte,
+}
+
+/// Options controlling the board and the `,`/`.` instructions' behavior
+#[derive(Debug, Clone, Copy)]
+pub struct RunOptions {
+    /// The number of cells the board is allocated with up front; it still grows on demand
+    pub initial_board_size: usize,
+    /// How `,` behaves once the input is exhausted
+    pub eof_policy: EofPolicy,
+    /// How `.` renders a cell's value
+    pub output_mode: OutputMode,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            initial_board_size: 32,
+            eof_policy: EofPolicy::Leave,
+            output_mode: OutputMode::Utf8,
+        }
+    }
+}
+
+/// A memory cell's value, normalized to either a valid unicode codepoint or a raw number
+#[derive(Debug, Clone, Copy)]
+pub enum Value {
+    /// A value that is a valid unicode codepoint
+    C(char),
+    /// A value that isn't a valid unicode codepoint
+    N(u32),
+}
+
+impl Value {
+    /// Normalize a raw cell value
+    pub fn from_cell(n: u32) -> Self {
+        char::from_u32(n).map_or(Value::N(n), Value::C)
+    }
+}
+
+/// A single compiled brainfuck operation
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    /// Add a (possibly negative) delta to the current cell, collapsing runs of `+`/`-`
+    Add(i32),
+    /// Move the cell cursor by a (possibly negative) delta, collapsing runs of `>`/`<`
+    Move(i32),
+    /// Print the current cell's value (`.`)
+    Print,
+    /// Read a byte into the current cell (`,`)
+    Input,
+    /// Clear the current cell, folded from the common `[-]` pattern
+    SetZero,
+    /// Jump past the matching `JumpIfNonZero` if the current cell is zero (`[`)
+    JumpIfZero(usize),
+    /// Jump back past the matching `JumpIfZero` if the current cell is non-zero (`]`)
+    JumpIfNonZero(usize),
+}
+
+/// An error produced while compiling a brainfuck program
+#[derive(Debug)]
+pub enum CompileError {
+    /// A `]` appeared without a matching `[`
+    UnmatchedClose,
+    /// A `[` was never closed by a matching `]`
+    UnmatchedOpen,
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::UnmatchedClose => write!(f, "unmatched `]`"),
+            CompileError::UnmatchedOpen => write!(f, "unmatched `[`"),
+        }
+    }
+}
+
+/// Compile raw brainfuck source into a compact op vector with resolved jump targets
+fn compile(bytes: &[u8]) -> Result<Vec<Op>, CompileError> {
+    let mut ops = Vec::new();
+    let mut jump_stack = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'[' if bytes.get(i + 1) == Some(&b'-') && bytes.get(i + 2) == Some(&b']') => {
+                ops.push(Op::SetZero);
+                i += 3;
+                continue;
+            }
+            b'[' => {
+                jump_stack.push(ops.len());
+                // placeholder, patched once the matching `]` is found
+                ops.push(Op::JumpIfZero(0));
+            }
+            b']' => {
+                let open = jump_stack.pop().ok_or(CompileError::UnmatchedClose)?;
+                ops.push(Op::JumpIfNonZero(open + 1));
+                ops[open] = Op::JumpIfZero(ops.len());
+            }
+            b'+' => push_add(&mut ops, 1),
+            b'-' => push_add(&mut ops, -1),
+            b'>' => push_move(&mut ops, 1),
+            b'<' => push_move(&mut ops, -1),
+            b'.' => ops.push(Op::Print),
+            b',' => ops.push(Op::Input),
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    if !jump_stack.is_empty() {
+        return Err(CompileError::UnmatchedOpen);
+    }
+
+    Ok(ops)
+}
+
+/// Push an `Add`, folding onto the previous op if it is also an `Add`
+fn push_add(ops: &mut Vec<Op>, delta: i32) {
+    if let Some(Op::Add(n)) = ops.last_mut() {
+        *n += delta;
+    } else {
+        ops.push(Op::Add(delta));
+    }
+}
+
+/// Push a `Move`, folding onto the previous op if it is also a `Move`
+fn push_move(ops: &mut Vec<Op>, delta: i32) {
+    if let Some(Op::Move(n)) = ops.last_mut() {
+        *n += delta;
+    } else {
+        ops.push(Op::Move(delta));
+    }
+}
+
+/// A brainfuck vm holding the memory state of a running program: the board and the cursor
+#[derive(Debug)]
+pub struct BfVm {
+    /// The board which actually stores the programms memory
+    pub board: Vec<u32>,
+    /// The current cell the program is accessing
+    pub cur_cell_idx: usize,
+}
+
+impl BfVm {
+    /// Create a fresh vm with a board of `initial_board_size` cells (at least one), all zeroed
+    pub fn new(initial_board_size: usize) -> Self {
+        Self {
+            board: vec![0; initial_board_size.max(1)],
+            cur_cell_idx: 0,
+        }
+    }
+
+    /// Get a mutable reference to the current memory cell
+    pub fn cur_cell_mut(&mut self) -> &mut u32 {
+        self.board
+            .get_mut(self.cur_cell_idx)
+            .unwrap_or_else(|| unreachable!("cur_cell should always be inside the board"))
+    }
+}
+
+/// Compile and execute a brainfuck `program`, reading `,` from `input` and writing `.` to
+/// `output`, using the default [`RunOptions`]
+pub fn run<R: Read, W: Write>(
+    program: &[u8],
+    input: R,
+    output: W,
+) -> Result<(), RunError<R::Error, W::Error>> {
+    run_with_options(program, input, output, RunOptions::default())
+}
+
+/// Compile and execute a brainfuck `program` with the given [`RunOptions`]
+pub fn run_with_options<R: Read, W: Write>(
+    program: &[u8],
+    input: R,
+    output: W,
+    options: RunOptions,
+) -> Result<(), RunError<R::Error, W::Error>> {
+    let ops = compile(program).map_err(RunError::Compile)?;
+    execute(&ops, input, output, options)
+}
+
+/// Execute a compiled op vector, with `pc` jumping in O(1) via the precomputed jump table
+fn execute<R: Read, W: Write>(
+    ops: &[Op],
+    mut input: R,
+    mut output: W,
+    options: RunOptions,
+) -> Result<(), RunError<R::Error, W::Error>> {
+    let mut vm = BfVm::new(options.initial_board_size);
+    let mut pc = 0usize;
+
+    while pc < ops.len() {
+        match ops[pc] {
+            Op::Add(n) => {
+                let cell = vm.cur_cell_mut();
+                *cell = if n >= 0 {
+                    cell.saturating_add(n as u32)
+                } else {
+                    cell.saturating_sub(n.unsigned_abs())
+                };
+            }
+            Op::Move(n) => {
+                if n >= 0 {
+                    let new_idx = vm.cur_cell_idx + n as usize;
+                    if new_idx >= vm.board.len() {
+                        vm.board.resize(new_idx + 1, 0);
+                    }
+                    vm.cur_cell_idx = new_idx;
+                } else {
+                    vm.cur_cell_idx = vm.cur_cell_idx.saturating_sub(n.unsigned_abs() as usize);
+                }
+            }
+            Op::SetZero => *vm.cur_cell_mut() = 0,
+            Op::Print => {
+                let n = *vm.cur_cell_mut();
+                match options.output_mode {
+                    OutputMode::Utf8 => match Value::from_cell(n) {
+                        Value::C(c) => {
+                            let mut buf = [0u8; 4];
+                            output
+                                .write_all(c.encode_utf8(&mut buf).as_bytes())
+                                .map_err(RunError::Output)?;
+                        }
+                        Value::N(n) => output
+                            .write_all(format!("r({})", n).as_bytes())
+                            .map_err(RunError::Output)?,
+                    },
+                    OutputMode::Decimal => output
+                        .write_all(format!("{} ", n).as_bytes())
+                        .map_err(RunError::Output)?,
+                    OutputMode::Byte => output.write_all(&[n as u8]).map_err(RunError::Output)?,
+                }
+            }
+            Op::Input => match input.read_byte().map_err(RunError::Input)? {
+                Some(b) => *vm.cur_cell_mut() = u32::from(b),
+                None => match options.eof_policy {
+                    EofPolicy::Leave => {}
+                    EofPolicy::Zero => *vm.cur_cell_mut() = 0,
+                    EofPolicy::NegOne => *vm.cur_cell_mut() = u32::MAX,
+                },
+            },
+            Op::JumpIfZero(target) => {
+                if *vm.cur_cell_mut() == 0 {
+                    pc = target;
+                    continue;
+                }
+            }
+            Op::JumpIfNonZero(target) => {
+                if *vm.cur_cell_mut() != 0 {
+                    pc = target;
+                    continue;
+                }
+            }
+        }
+
+        pc += 1;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_zero_folds_the_clear_cell_loop() {
+        let ops = compile(b"+++[-]").expect("valid brainfuck");
+        assert!(matches!(ops.as_slice(), [Op::Add(3), Op::SetZero]));
+
+        let mut output = Vec::new();
+        execute(&ops, &b""[..], &mut output, RunOptions::default()).expect("run succeeds");
+        assert_eq!(output, b"");
+    }
+
+    #[test]
+    fn run_writes_utf8_output() {
+        let program = "+".repeat(65) + ".";
+        let mut output = Vec::new();
+        run(program.as_bytes(), &b""[..], &mut output).expect("run succeeds");
+        assert_eq!(output, b"A");
+    }
+}